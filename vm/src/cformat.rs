@@ -7,7 +7,7 @@ use crate::pyobject::{
 use crate::vm::VirtualMachine;
 use num_bigint::{BigInt, Sign};
 use num_traits::cast::ToPrimitive;
-use num_traits::Signed;
+use num_traits::{Signed, Zero};
 use std::iter::{Enumerate, Peekable};
 use std::str::FromStr;
 use std::{cmp, fmt};
@@ -48,7 +48,7 @@ impl fmt::Display for CFormatError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CFormatPreconversor {
     Repr,
     Str,
@@ -56,27 +56,27 @@ enum CFormatPreconversor {
     Bytes,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CFormatCase {
     Lowercase,
     Uppercase,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CNumberType {
     Decimal,
     Octal,
     Hex(CFormatCase),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CFloatType {
     Exponent(CFormatCase),
     PointDecimal,
     General(CFormatCase),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CFormatType {
     Number(CNumberType),
     Float(CFloatType),
@@ -84,6 +84,12 @@ enum CFormatType {
     String(CFormatPreconversor),
 }
 
+// CPython's printf-style `%`-format grammar only ever fills with a space or `0` and
+// only ever left- or right-justifies (see the conversion flags in
+// https://docs.python.org/3/library/stdtypes.html#printf-style-string-formatting) —
+// there is no syntax for an arbitrary fill character or for center alignment, unlike
+// e.g. `str.format`'s `{:*^10}`. Supporting those here would format strings CPython
+// itself can't produce, so they're intentionally not implemented.
 bitflags! {
     struct CConversionFlags: u32 {
         const ALTERNATE_FORM = 0b0000_0001;
@@ -106,13 +112,13 @@ impl CConversionFlags {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum CFormatQuantity {
     Amount(usize),
     FromValuesTuple,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct CFormatSpec {
     mapping_key: Option<String>,
     flags: CConversionFlags,
@@ -123,6 +129,22 @@ struct CFormatSpec {
     // chars_consumed: usize,
 }
 
+// Strips trailing zeros (and a dangling decimal point) from the fractional part of a
+// formatted number, leaving any `e±NN` exponent suffix untouched.
+fn strip_trailing_zeros_and_dot(formatted: &str) -> String {
+    let (mantissa, exponent) = match formatted.find(['e', 'E']) {
+        Some(pos) => (&formatted[..pos], &formatted[pos..]),
+        None => (formatted, ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        let trimmed = mantissa.trim_end_matches('0');
+        trimmed.trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{}{}", mantissa, exponent)
+}
+
 impl CFormatSpec {
     fn parse<I: Iterator<Item = char>>(iter: &mut ParseIter<I>) -> Result<Self, ParsingError> {
         let mapping_key = parse_spec_mapping_key(iter)?;
@@ -186,6 +208,36 @@ impl CFormatSpec {
         }
     }
 
+    // Shared padding for numeric conversions (`%d`/`%x`/`%o`/`%f`/`%e`/`%g`/...). When
+    // zero-padding applies, the fill goes between `sign_and_prefix` and `magnitude` so
+    // e.g. `%08d` on -5 renders `-0000005` rather than zero-filling the sign away; the
+    // `-` (left-adjust) flag always overrides `0`. Otherwise the whole
+    // `sign_and_prefix + magnitude` is padded with spaces per the field width.
+    // `allow_zero_pad` lets a caller suppress zero-padding outright regardless of the
+    // `0` flag; callers that always honor `0` (every numeric conversion, finite or not)
+    // just pass `true`.
+    fn pad_integral(
+        &self,
+        sign_and_prefix: &str,
+        magnitude: String,
+        allow_zero_pad: bool,
+    ) -> String {
+        if allow_zero_pad && self.flags.contains(CConversionFlags::ZERO_PAD) {
+            let fill_char = if self.flags.contains(CConversionFlags::LEFT_ADJUST) {
+                ' ' // '-' overrides the '0' conversion if both are given
+            } else {
+                '0'
+            };
+            format!(
+                "{}{}",
+                sign_and_prefix,
+                self.fill_string(magnitude, fill_char, Some(sign_and_prefix.chars().count()))
+            )
+        } else {
+            self.fill_string(format!("{}{}", sign_and_prefix, magnitude), ' ', None)
+        }
+    }
+
     fn format_string_with_precision(
         &self,
         string: String,
@@ -246,7 +298,7 @@ impl CFormatSpec {
             ""
         };
 
-        let magnitude_string: String = match self.format_type {
+        let mut magnitude_string: String = match self.format_type {
             CFormatType::Number(Decimal) => magnitude.to_str_radix(10),
             CFormatType::Number(Octal) => magnitude.to_str_radix(8),
             CFormatType::Number(Hex(Lowercase)) => magnitude.to_str_radix(16),
@@ -258,55 +310,67 @@ impl CFormatSpec {
             _ => unreachable!(), // Should not happen because caller has to make sure that this is a number
         };
 
+        // The precision, when given, is the minimum number of digits: left-pad with
+        // zeros. A precision still combines with the `0` flag for width padding
+        // (CPython honors both, e.g. `'%010.2d' % 3` -> `'0000000003'`).
+        if let Some(CFormatQuantity::Amount(precision)) = self.precision {
+            if magnitude_string.len() < precision {
+                magnitude_string = format!(
+                    "{}{}",
+                    "0".repeat(precision - magnitude_string.len()),
+                    magnitude_string
+                );
+            }
+        }
+
         let sign_string = match num.sign() {
             Sign::Minus => "-",
             _ => self.flags.sign_string(),
         };
 
-        if self.flags.contains(CConversionFlags::ZERO_PAD) {
-            let fill_char = if !self.flags.contains(CConversionFlags::LEFT_ADJUST) {
-                '0'
-            } else {
-                ' ' // '-' overrides the '0' conversion if both are given
-            };
-            let signed_prefix = format!("{}{}", sign_string, prefix);
-            format!(
-                "{}{}",
-                signed_prefix,
-                self.fill_string(
-                    magnitude_string,
-                    fill_char,
-                    Some(signed_prefix.chars().count())
-                )
-            )
-        } else {
-            self.fill_string(
-                format!("{}{}{}", sign_string, prefix, magnitude_string),
-                ' ',
-                None,
-            )
-        }
+        let signed_prefix = format!("{}{}", sign_string, prefix);
+        self.pad_integral(&signed_prefix, magnitude_string, true)
     }
 
-    fn normalize_float(&self, num: f64) -> (f64, i32) {
-        let mut fraction = num;
-        let mut exponent = 0;
-        loop {
-            if fraction >= 10.0 {
-                fraction /= 10.0;
-                exponent += 1;
-            } else if fraction < 1.0 && fraction > 0.0 {
-                fraction *= 10.0;
-                exponent -= 1;
-            } else {
-                break;
-            }
+    // Derives the decimal exponent for `%e`/`%E` directly, instead of looping the value
+    // through repeated multiplication/division by 10.0 (which is O(exponent) and drifts
+    // by a ULP for very large/small magnitudes). Rounding the value to `precision`
+    // fractional digits via Rust's exponential formatter first keeps the mantissa and
+    // exponent in sync, so a carry to 10.0 (e.g. 9.999 at precision 2) bumps the exponent.
+    // The mantissa is kept as the formatted string (not reparsed into an `f64`): at high
+    // precision the rendered mantissa can carry more significant digits than an `f64`
+    // can hold exactly, and round-tripping it through another parse loses a ULP.
+    fn normalize_float(&self, num: f64, precision: usize) -> (String, i32) {
+        if num == 0.0 {
+            return (format!("{:.*}", precision, 0.0), 0);
         }
-
-        (fraction, exponent)
+        let formatted = format!("{:.*e}", precision, num);
+        let e_index = formatted.find('e').unwrap();
+        let mantissa = formatted[..e_index].to_owned();
+        let exponent: i32 = formatted[e_index + 1..].parse().unwrap();
+        (mantissa, exponent)
     }
 
     pub(crate) fn format_float(&self, num: f64) -> Result<String, String> {
+        if !num.is_finite() {
+            // CPython does zero-pad inf/nan like any other numeric conversion, and the
+            // sign flags apply to nan the same as to a positive number (nan itself has
+            // no sign, so it's never rendered with a literal `-`).
+            let is_upper = self.format_char.is_ascii_uppercase();
+            let sign_string = if !num.is_nan() && num.is_sign_negative() {
+                "-"
+            } else {
+                self.flags.sign_string()
+            };
+            let magnitude_string = match (num.is_nan(), is_upper) {
+                (true, false) => "nan",
+                (true, true) => "NAN",
+                (false, false) => "inf",
+                (false, true) => "INF",
+            };
+            return Ok(self.pad_integral(sign_string, magnitude_string.to_owned(), true));
+        }
+
         let sign_string = if num.is_sign_positive() {
             self.flags.sign_string()
         } else {
@@ -322,39 +386,61 @@ impl CFormatSpec {
                 let magnitude = num.abs();
                 Ok(format!("{:.*}", precision, magnitude))
             }
-            CFormatType::Float(CFloatType::Exponent(_)) => {
+            CFormatType::Float(CFloatType::Exponent(case)) => {
                 let precision = match self.precision {
                     Some(CFormatQuantity::Amount(p)) => p,
                     _ => 6,
                 };
-                let (fraction, exponent) = self.normalize_float(num.abs());
-                Ok(format!("{:.*}e{:+03}", precision, fraction, exponent))
+                let (fraction, exponent) = self.normalize_float(num.abs(), precision);
+                let e_char = match case {
+                    CFormatCase::Lowercase => 'e',
+                    CFormatCase::Uppercase => 'E',
+                };
+                Ok(format!("{}{}{:+03}", fraction, e_char, exponent))
             }
-            CFormatType::Float(CFloatType::General(_)) => {
-                Err("Not yet implemented for %g and %G".to_owned())
+            CFormatType::Float(CFloatType::General(case)) => {
+                let precision = match self.precision {
+                    Some(CFormatQuantity::Amount(p)) => p,
+                    _ => 6,
+                };
+                let precision = cmp::max(precision, 1);
+                let magnitude = num.abs();
+
+                // Reuse the %e exponent computation: a precision of `precision` significant
+                // digits is one digit before the point plus `precision - 1` after it.
+                let (fraction, exponent) = self.normalize_float(magnitude, precision - 1);
+
+                let e_char = match case {
+                    CFormatCase::Lowercase => 'e',
+                    CFormatCase::Uppercase => 'E',
+                };
+
+                let mut magnitude_string = if exponent < -4 || exponent >= precision as i32 {
+                    format!("{}{}{:+03}", fraction, e_char, exponent)
+                } else {
+                    let fractional_precision = (precision as i32 - 1 - exponent).max(0) as usize;
+                    format!("{:.*}", fractional_precision, magnitude)
+                };
+
+                if self.flags.contains(CConversionFlags::ALTERNATE_FORM) {
+                    // `#` always keeps a decimal point, even when there are no
+                    // fractional digits to begin with (e.g. `%#.1g` % 1.0 -> "1.").
+                    if !magnitude_string.contains('.') {
+                        let insert_at = magnitude_string
+                            .find(['e', 'E'])
+                            .unwrap_or(magnitude_string.len());
+                        magnitude_string.insert(insert_at, '.');
+                    }
+                } else {
+                    magnitude_string = strip_trailing_zeros_and_dot(&magnitude_string);
+                }
+
+                Ok(magnitude_string)
             }
             _ => unreachable!(),
         }?;
 
-        let formatted = if self.flags.contains(CConversionFlags::ZERO_PAD) {
-            let fill_char = if !self.flags.contains(CConversionFlags::LEFT_ADJUST) {
-                '0'
-            } else {
-                ' '
-            };
-            format!(
-                "{}{}",
-                sign_string,
-                self.fill_string(
-                    magnitude_string,
-                    fill_char,
-                    Some(sign_string.chars().count())
-                )
-            )
-        } else {
-            self.fill_string(format!("{}{}", sign_string, magnitude_string), ' ', None)
-        };
-        Ok(formatted)
+        Ok(self.pad_integral(sign_string, magnitude_string, true))
     }
 
     fn bytes_format(&self, vm: &VirtualMachine, obj: PyObjectRef) -> PyResult<Vec<u8>> {
@@ -582,39 +668,45 @@ impl CFormatBytes {
         let mut iter = bytes.iter().map(|&x| x as char).enumerate().peekable();
         Self::parse(&mut iter)
     }
-    pub(crate) fn format(
-        &mut self,
+    pub(crate) fn format(&self, vm: &VirtualMachine, values_obj: PyObjectRef) -> PyResult<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.estimated_len());
+        self.format_into(&mut buf, vm, values_obj)?;
+        Ok(buf)
+    }
+
+    // Formats directly into a caller-supplied buffer so hot `%` loops can reuse a
+    // single scratch `Vec` instead of allocating a fresh one per interpolation.
+    pub(crate) fn format_into(
+        &self,
+        buf: &mut Vec<u8>,
         vm: &VirtualMachine,
         values_obj: PyObjectRef,
-    ) -> PyResult<Vec<u8>> {
+    ) -> PyResult<()> {
         fn try_update_quantity_from_tuple(
             vm: &VirtualMachine,
             elements: &mut dyn Iterator<Item = PyObjectRef>,
             q: &mut Option<CFormatQuantity>,
-            mut tuple_index: usize,
-        ) -> PyResult<usize> {
+        ) -> PyResult<()> {
             match q {
                 Some(CFormatQuantity::FromValuesTuple) => match elements.next() {
                     Some(width_obj) => {
-                        tuple_index += 1;
                         if !width_obj.isinstance(&vm.ctx.types.int_type) {
                             Err(vm.new_type_error("* wants int".to_owned()))
                         } else {
                             let i = int::get_value(&width_obj);
                             let i = int::try_to_primitive::<isize>(i, vm)? as usize;
                             *q = Some(CFormatQuantity::Amount(i));
-                            Ok(tuple_index)
+                            Ok(())
                         }
                     }
                     None => {
                         Err(vm.new_type_error("not enough arguments for format string".to_owned()))
                     }
                 },
-                _ => Ok(tuple_index),
+                _ => Ok(()),
             }
         }
 
-        let mut final_bytes = vec![];
         let num_specifiers = self
             .parts
             .iter()
@@ -655,62 +747,76 @@ impl CFormatBytes {
             }
         };
 
-        let mut tuple_index: usize = 0;
-        for (_, part) in &mut self.parts {
-            let mut result_bytes: Vec<u8> = match part {
+        // Build a single cursor over the values tuple up front instead of re-cloning
+        // and re-slicing it for every specifier.
+        let mut elements = if mapping_required {
+            Vec::new().into_iter().peekable()
+        } else {
+            tuple::get_value(&values).to_vec().into_iter().peekable()
+        };
+
+        for (_, part) in &self.parts {
+            match part {
                 CFormatPart::Spec(format_spec) => {
-                    // try to get the object
+                    // Work on a local copy so resolving `*` width/precision doesn't
+                    // mutate the template, which must stay reusable across calls.
+                    let mut format_spec = format_spec.clone();
                     let obj: PyObjectRef = match &format_spec.mapping_key {
                         Some(key) => {
                             // TODO: change the KeyError message to match the one in cpython
                             values.get_item(key, vm)?
                         }
                         None => {
-                            let mut elements = tuple::get_value(&values)
-                                .to_vec()
-                                .into_iter()
-                                .skip(tuple_index);
-
-                            tuple_index = try_update_quantity_from_tuple(
+                            try_update_quantity_from_tuple(
                                 vm,
                                 &mut elements,
                                 &mut format_spec.min_field_width,
-                                tuple_index,
                             )?;
-                            tuple_index = try_update_quantity_from_tuple(
+                            try_update_quantity_from_tuple(
                                 vm,
                                 &mut elements,
                                 &mut format_spec.precision,
-                                tuple_index,
                             )?;
 
-                            let obj = match elements.next() {
-                                Some(obj) => Ok(obj),
-                                None => Err(vm.new_type_error(
+                            elements.next().ok_or_else(|| {
+                                vm.new_type_error(
                                     "not enough arguments for format string".to_owned(),
-                                )),
-                            }?;
-                            tuple_index += 1;
-
-                            obj
+                                )
+                            })?
                         }
                     };
-                    format_spec.bytes_format(vm, obj)?
+                    buf.extend(format_spec.bytes_format(vm, obj)?);
                 }
-                CFormatPart::Literal(literal) => literal.clone().into_bytes(),
-            };
-            final_bytes.append(&mut result_bytes);
+                CFormatPart::Literal(literal) => buf.extend_from_slice(literal.as_bytes()),
+            }
         }
 
         // check that all arguments were converted
-        if (!mapping_required && tuple::get_value(&values).get(tuple_index).is_some())
+        if !mapping_required
+            && elements.peek().is_some()
             && !values_obj.isinstance(&vm.ctx.types.dict_type)
         {
             return Err(vm.new_type_error(
                 "not all arguments converted during string formatting".to_owned(),
             ));
         }
-        Ok(final_bytes)
+        Ok(())
+    }
+
+    // Rough size estimate for pre-sizing the output buffer: literal runs contribute their
+    // exact length, and specifiers guess from an explicit width or a small default (a
+    // width is only a minimum, so the output can be longer than this).
+    fn estimated_len(&self) -> usize {
+        self.parts
+            .iter()
+            .map(|(_, part)| match part {
+                CFormatPart::Literal(s) => s.len(),
+                CFormatPart::Spec(spec) => match spec.min_field_width {
+                    Some(CFormatQuantity::Amount(width)) => width,
+                    _ => 8,
+                },
+            })
+            .sum()
     }
 }
 
@@ -740,39 +846,45 @@ impl CFormatString {
             }),
         }
     }
-    pub(crate) fn format(
-        &mut self,
+    pub(crate) fn format(&self, vm: &VirtualMachine, values_obj: PyObjectRef) -> PyResult<String> {
+        let mut buf = String::with_capacity(self.estimated_len());
+        self.format_into(&mut buf, vm, values_obj)?;
+        Ok(buf)
+    }
+
+    // Formats directly into a caller-supplied buffer so hot `%` loops can reuse a
+    // single scratch `String` instead of allocating a fresh one per interpolation.
+    pub(crate) fn format_into(
+        &self,
+        buf: &mut String,
         vm: &VirtualMachine,
         values_obj: PyObjectRef,
-    ) -> PyResult<String> {
+    ) -> PyResult<()> {
         fn try_update_quantity_from_tuple(
             vm: &VirtualMachine,
             elements: &mut dyn Iterator<Item = PyObjectRef>,
             q: &mut Option<CFormatQuantity>,
-            mut tuple_index: usize,
-        ) -> PyResult<usize> {
+        ) -> PyResult<()> {
             match q {
                 Some(CFormatQuantity::FromValuesTuple) => match elements.next() {
                     Some(width_obj) => {
-                        tuple_index += 1;
                         if !width_obj.isinstance(&vm.ctx.types.int_type) {
                             Err(vm.new_type_error("* wants int".to_owned()))
                         } else {
                             let i = int::get_value(&width_obj);
                             let i = int::try_to_primitive::<isize>(i, vm)? as usize;
                             *q = Some(CFormatQuantity::Amount(i));
-                            Ok(tuple_index)
+                            Ok(())
                         }
                     }
                     None => {
                         Err(vm.new_type_error("not enough arguments for format string".to_owned()))
                     }
                 },
-                _ => Ok(tuple_index),
+                _ => Ok(()),
             }
         }
 
-        let mut final_string = String::new();
         let num_specifiers = self
             .parts
             .iter()
@@ -813,62 +925,76 @@ impl CFormatString {
             }
         };
 
-        let mut tuple_index: usize = 0;
-        for (_, part) in &mut self.parts {
-            let result_string: String = match part {
+        // Build a single cursor over the values tuple up front instead of re-cloning
+        // and re-slicing it for every specifier.
+        let mut elements = if mapping_required {
+            Vec::new().into_iter().peekable()
+        } else {
+            tuple::get_value(&values).to_vec().into_iter().peekable()
+        };
+
+        for (_, part) in &self.parts {
+            match part {
                 CFormatPart::Spec(format_spec) => {
-                    // try to get the object
+                    // Work on a local copy so resolving `*` width/precision doesn't
+                    // mutate the template, which must stay reusable across calls.
+                    let mut format_spec = format_spec.clone();
                     let obj: PyObjectRef = match &format_spec.mapping_key {
                         Some(key) => {
                             // TODO: change the KeyError message to match the one in cpython
                             values.get_item(key, vm)?
                         }
                         None => {
-                            let mut elements = tuple::get_value(&values)
-                                .to_vec()
-                                .into_iter()
-                                .skip(tuple_index);
-
-                            tuple_index = try_update_quantity_from_tuple(
+                            try_update_quantity_from_tuple(
                                 vm,
                                 &mut elements,
                                 &mut format_spec.min_field_width,
-                                tuple_index,
                             )?;
-                            tuple_index = try_update_quantity_from_tuple(
+                            try_update_quantity_from_tuple(
                                 vm,
                                 &mut elements,
                                 &mut format_spec.precision,
-                                tuple_index,
                             )?;
 
-                            let obj = match elements.next() {
-                                Some(obj) => Ok(obj),
-                                None => Err(vm.new_type_error(
+                            elements.next().ok_or_else(|| {
+                                vm.new_type_error(
                                     "not enough arguments for format string".to_owned(),
-                                )),
-                            }?;
-                            tuple_index += 1;
-
-                            obj
+                                )
+                            })?
                         }
                     };
-                    format_spec.format(vm, obj)
+                    buf.push_str(&format_spec.format(vm, obj)?);
                 }
-                CFormatPart::Literal(literal) => Ok(literal.clone()),
-            }?;
-            final_string.push_str(&result_string);
+                CFormatPart::Literal(literal) => buf.push_str(literal),
+            }
         }
 
         // check that all arguments were converted
-        if (!mapping_required && tuple::get_value(&values).get(tuple_index).is_some())
+        if !mapping_required
+            && elements.peek().is_some()
             && !values_obj.isinstance(&vm.ctx.types.dict_type)
         {
             return Err(vm.new_type_error(
                 "not all arguments converted during string formatting".to_owned(),
             ));
         }
-        Ok(final_string)
+        Ok(())
+    }
+
+    // Rough size estimate for pre-sizing the output buffer: literal runs contribute their
+    // exact length, and specifiers guess from an explicit width or a small default (a
+    // width is only a minimum, so the output can be longer than this).
+    fn estimated_len(&self) -> usize {
+        self.parts
+            .iter()
+            .map(|(_, part)| match part {
+                CFormatPart::Literal(s) => s.len(),
+                CFormatPart::Spec(spec) => match spec.min_field_width {
+                    Some(CFormatQuantity::Amount(width)) => width,
+                    _ => 8,
+                },
+            })
+            .sum()
     }
 }
 
@@ -995,6 +1121,9 @@ fn parse_spec_mapping_key<I: Iterator<Item = char>>(
     Ok(None)
 }
 
+// `# 0 - space +` are the only conversion flags CPython's `%`-format grammar defines;
+// there's no flag for a custom fill character or center alignment (see the
+// `CConversionFlags` comment above), so none is parsed here.
 fn parse_flags<I: Iterator<Item = char>>(iter: &mut ParseIter<I>) -> CConversionFlags {
     let mut flags = CConversionFlags::empty();
     while let Some(&(_, c)) = iter.peek() {
@@ -1045,9 +1174,7 @@ fn parse_format_type<I: Iterator<Item = char>>(
         'E' => CFormatType::Float(Exponent(Uppercase)),
         'f' => CFormatType::Float(PointDecimal),
         'F' => CFormatType::Float(PointDecimal),
-        //TODO: Same as "e" if exponent is greater than -4 or less than precision, "f" otherwise.
         'g' => CFormatType::Float(General(Lowercase)),
-        //TODO: Same as "E" if exponent is greater than -4 or less than precision, "F" otherwise.
         'G' => CFormatType::Float(General(Uppercase)),
         'c' => CFormatType::Character,
         'r' => CFormatType::String(CFormatPreconversor::Repr),
@@ -1272,6 +1399,29 @@ mod tests {
                 .format_number(&BigInt::from(0x1337)),
             "0x1337    ".to_owned()
         );
+        // The `0` flag still applies the width fill even when a precision is given.
+        assert_eq!(
+            "%010.2d"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_number(&BigInt::from(3)),
+            "0000000003".to_owned()
+        );
+        assert_eq!(
+            "%+010.2d"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_number(&BigInt::from(-3)),
+            "-000000003".to_owned()
+        );
+        // A precision of 0 on a value of 0 still renders the digit "0".
+        assert_eq!(
+            "%.0d"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_number(&BigInt::from(0)),
+            "0".to_owned()
+        );
     }
 
     #[test]
@@ -1313,6 +1463,84 @@ mod tests {
                 .ok(),
             Some("1.234568".to_owned())
         );
+        // inf/nan zero-pad like any other numeric conversion, and nan still honors the
+        // sign flags (it just never renders a literal `-`).
+        assert_eq!(
+            "%06.2f"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(f64::INFINITY)
+                .ok(),
+            Some("000inf".to_owned())
+        );
+        assert_eq!(
+            "%+f"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(f64::NAN)
+                .ok(),
+            Some("+nan".to_owned())
+        );
+        // `pad_integral` is the shared zero/space padding path for every numeric
+        // conversion; pin it against a float width/precision combo too, not just `%d`.
+        assert_eq!(
+            "%010.2f"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(3.14)
+                .ok(),
+            Some("0000003.14".to_owned())
+        );
+        assert_eq!(
+            "%-010.2f"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(3.14)
+                .ok(),
+            Some("3.14      ".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_parse_and_format_general_float() {
+        assert_eq!(
+            "%g".parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(1234567.0)
+                .ok(),
+            Some("1.23457e+06".to_owned())
+        );
+        assert_eq!(
+            "%G".parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(1234567.0)
+                .ok(),
+            Some("1.23457E+06".to_owned())
+        );
+        assert_eq!(
+            "%g".parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(0.0001234)
+                .ok(),
+            Some("0.0001234".to_owned())
+        );
+        // `#` keeps a decimal point even when there are no fractional digits at all.
+        assert_eq!(
+            "%#.1g"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(1.0)
+                .ok(),
+            Some("1.".to_owned())
+        );
+        assert_eq!(
+            "%#g"
+                .parse::<CFormatSpec>()
+                .unwrap()
+                .format_float(1e16)
+                .ok(),
+            Some("1.00000e+16".to_owned())
+        );
     }
 
     #[test]